@@ -1,17 +1,36 @@
 #![recursion_limit = "2048"]
 #[macro_use]
 extern crate yew;
+extern crate base64;
 extern crate failure;
+extern crate flate2;
 extern crate marshal;
+#[macro_use]
 extern crate stdweb;
 #[macro_use]
 extern crate serde_json;
+extern crate serde_yaml;
+extern crate toml;
+extern crate chacha20poly1305;
+extern crate rand;
 
 use std::fmt;
+use std::io::{Read, Write};
 use std::str::FromStr;
 
 use failure::{err_msg, Error, ResultExt};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use stdweb::unstable::TryInto;
+use yew::format::{Nothing, Text};
 use yew::prelude::*;
+use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::ChangeData;
 
 use marshal::processor::PiiConfig as ProcessorPiiConfig;
 use marshal::protocol::{Annotated, Event, Value};
@@ -81,6 +100,137 @@ macro_rules! web_panic {
     }}
 }
 
+fn get_location_fragment() -> String {
+    let fragment: String = js! {
+        return window.location.hash.replace(/^#/, "");
+    }.try_into()
+        .unwrap_or_default();
+    fragment
+}
+
+fn set_location_fragment(fragment: &str) {
+    js! {
+        window.location.hash = @{fragment};
+    }
+}
+
+fn encode_permalink(event: &str, config: &str) -> Result<String, Error> {
+    let payload = json!({ "event": event, "config": config }).to_string();
+
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(payload.as_bytes())
+        .context("Failed to compress permalink")?;
+    let compressed = encoder.finish().context("Failed to compress permalink")?;
+
+    Ok(base64::encode_config(&compressed, base64::URL_SAFE_NO_PAD))
+}
+
+fn decode_permalink(fragment: &str) -> Result<(String, String), Error> {
+    let compressed = base64::decode_config(fragment, base64::URL_SAFE_NO_PAD)
+        .context("Failed to decode permalink")?;
+
+    let mut decoder = DeflateDecoder::new(&compressed[..]);
+    let mut payload = String::new();
+    decoder
+        .read_to_string(&mut payload)
+        .context("Failed to decompress permalink")?;
+
+    let value: serde_json::Value =
+        serde_json::from_str(&payload).context("Failed to parse permalink")?;
+
+    let event = value
+        .get("event")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| err_msg("Permalink is missing an event"))?
+        .to_owned();
+    let config = value
+        .get("config")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| err_msg("Permalink is missing a PII config"))?
+        .to_owned();
+
+    Ok((event, config))
+}
+
+fn get_share_source_url() -> Option<String> {
+    let src: String = js! {
+        var params = new URLSearchParams(window.location.search);
+        return params.get("src") || null;
+    }.try_into()
+        .unwrap_or_default();
+
+    if src.is_empty() {
+        None
+    } else {
+        Some(src)
+    }
+}
+
+fn decode_encrypted_fragment(fragment: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    if !fragment.starts_with("k=") {
+        return None;
+    }
+    let combined = base64::decode_config(&fragment[2..], base64::URL_SAFE_NO_PAD).ok()?;
+
+    if combined.len() != 32 + 24 {
+        return None;
+    }
+
+    Some((combined[..32].to_vec(), combined[32..].to_vec()))
+}
+
+fn encrypt_snapshot(event: &str, config: &str) -> Result<(String, String), Error> {
+    let payload = json!({ "event": event, "config": config }).to_string();
+
+    let mut key_bytes = [0u8; 32];
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut key_bytes);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key_bytes));
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), payload.as_bytes())
+        .map_err(|_| err_msg("Failed to encrypt snapshot"))?;
+
+    let mut fragment_bytes = Vec::with_capacity(key_bytes.len() + nonce_bytes.len());
+    fragment_bytes.extend_from_slice(&key_bytes);
+    fragment_bytes.extend_from_slice(&nonce_bytes);
+
+    let fragment = format!(
+        "k={}",
+        base64::encode_config(&fragment_bytes, base64::URL_SAFE_NO_PAD)
+    );
+    let ciphertext_b64 = base64::encode_config(&ciphertext, base64::STANDARD);
+
+    Ok((ciphertext_b64, fragment))
+}
+
+fn decrypt_snapshot(ciphertext_b64: &str, key: &[u8], nonce: &[u8]) -> Result<(String, String), Error> {
+    let ciphertext = base64::decode(ciphertext_b64.trim()).context("Failed to decode ciphertext")?;
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext.as_ref())
+        .map_err(|_| err_msg("Failed to decrypt snapshot \u{2014} wrong key, or the data was tampered with"))?;
+
+    let value: serde_json::Value =
+        serde_json::from_slice(&plaintext).context("Failed to parse decrypted snapshot")?;
+
+    let event = value
+        .get("event")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| err_msg("Decrypted snapshot is missing an event"))?
+        .to_owned();
+    let config = value
+        .get("config")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| err_msg("Decrypted snapshot is missing a PII config"))?
+        .to_owned();
+
+    Ok((event, config))
+}
+
 fn get_value_by_path<'a>(value: &'a Annotated<Value>, path: &str) -> Option<&'a Annotated<Value>> {
     if path.is_empty() || path == "." {
         Some(value)
@@ -107,6 +257,43 @@ fn get_value_by_path<'a>(value: &'a Annotated<Value>, path: &str) -> Option<&'a
     }
 }
 
+/// Like `get_value_by_path`, but returns an `Err` instead of panicking on a
+/// malformed path. Use this for paths typed by a user (e.g. test case
+/// corpus entries) rather than paths derived from clicking on a real JSON
+/// node, where a malformed path can never occur.
+fn try_get_value_by_path<'a>(
+    value: &'a Annotated<Value>,
+    path: &str,
+) -> Result<Option<&'a Annotated<Value>>, Error> {
+    if path.is_empty() || path == "." {
+        return Ok(Some(value));
+    }
+
+    let parts: Vec<_> = path.splitn(2, '.').collect();
+    let segment = parts
+        .get(0)
+        .cloned()
+        .ok_or_else(|| err_msg(format!("splitn returned zero-sized sequence: {:?}", path)))?;
+
+    if segment.is_empty() {
+        return Err(err_msg(format!("Empty path segment in {:?}", path)));
+    }
+
+    let new_value = match value.value() {
+        Some(Value::Array(array)) => array.get(
+            usize::from_str(segment)
+                .map_err(|e| err_msg(format!("Failed to parse array index {:?}: {:?}", segment, e)))?,
+        ),
+        Some(Value::Map(map)) => map.get(segment),
+        _ => None,
+    };
+
+    match new_value {
+        Some(new_value) => try_get_value_by_path(new_value, parts.get(1).cloned().unwrap_or("")),
+        None => Ok(None),
+    }
+}
+
 fn get_rule_suggestions_for_value(
     event: &SensitiveEvent,
     config: &PiiConfig,
@@ -220,10 +407,75 @@ fn get_rule_suggestions_for_value(
     Ok(rv)
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    fn label(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "json" => Ok(ConfigFormat::Json),
+            "yaml" => Ok(ConfigFormat::Yaml),
+            "toml" => Ok(ConfigFormat::Toml),
+            _ => Err(err_msg(format!("Unknown config format: {:?}", s))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct PiiConfig(String);
 
 impl PiiConfig {
+    fn from_format(raw: &str, format: ConfigFormat) -> Result<PiiConfig, Error> {
+        let value: serde_json::Value = match format {
+            ConfigFormat::Json => {
+                serde_json::from_str(raw).context("Failed to parse JSON PII config")?
+            }
+            ConfigFormat::Yaml => {
+                serde_yaml::from_str(raw).context("Failed to parse YAML PII config")?
+            }
+            ConfigFormat::Toml => {
+                toml::from_str(raw).context("Failed to parse TOML PII config")?
+            }
+        };
+
+        Ok(PiiConfig(
+            serde_json::to_string_pretty(&value).context("Failed to serialize PII config")?,
+        ))
+    }
+
+    fn to_format(&self, format: ConfigFormat) -> Result<String, Error> {
+        let value: serde_json::Value =
+            serde_json::from_str(&self.0).context("Failed to parse PII config")?;
+
+        Ok(match format {
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(&value).context("Failed to serialize JSON config")?
+            }
+            ConfigFormat::Yaml => {
+                serde_yaml::to_string(&value).context("Failed to serialize YAML config")?
+            }
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(&value).context("Failed to serialize TOML config")?
+            }
+        })
+    }
+
     fn strip_event(&self, event: &SensitiveEvent) -> Result<StrippedEvent, Error> {
         let config =
             ProcessorPiiConfig::from_json(&self.0).context("Failed to parse PII config")?;
@@ -246,9 +498,86 @@ impl PiiConfig {
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct TestCase {
+    name: String,
+    event_json: String,
+    path: String,
+    expected: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum TestOutcome {
+    Pass,
+    Fail(String),
+    Error(String),
+}
+
+impl TestCase {
+    fn run(&self, config: &PiiConfig) -> TestOutcome {
+        let event = match SensitiveEvent::from_json(&self.event_json) {
+            Ok(x) => x,
+            Err(e) => return TestOutcome::Error(format!("Failed to parse event: {:?}", e)),
+        };
+
+        let stripped = match config.strip_event(&event) {
+            Ok(x) => x,
+            Err(e) => return TestOutcome::Error(format!("{:?}", e)),
+        };
+
+        let actual = match try_get_value_by_path(&stripped, &self.path) {
+            Ok(value) => value
+                .and_then(|x| x.to_json().ok())
+                .unwrap_or_else(|| "null".to_owned()),
+            Err(e) => return TestOutcome::Error(format!("Bad path {:?}: {:?}", self.path, e)),
+        };
+
+        if actual.trim() == self.expected.trim() {
+            TestOutcome::Pass
+        } else {
+            TestOutcome::Fail(actual)
+        }
+    }
+
+    fn to_json_value(&self) -> serde_json::Value {
+        json!({
+            "name": self.name,
+            "event": self.event_json,
+            "path": self.path,
+            "expected": self.expected,
+        })
+    }
+
+    fn from_json_value(value: &serde_json::Value) -> Option<TestCase> {
+        Some(TestCase {
+            name: value.get("name")?.as_str()?.to_owned(),
+            event_json: value.get("event")?.as_str()?.to_owned(),
+            path: value.get("path")?.as_str()?.to_owned(),
+            expected: value.get("expected")?.as_str()?.to_owned(),
+        })
+    }
+}
+
+fn test_cases_to_json(cases: &[TestCase]) -> String {
+    let value = serde_json::Value::Array(cases.iter().map(TestCase::to_json_value).collect());
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+fn test_cases_from_json(raw: &str) -> Result<Vec<TestCase>, Error> {
+    let value: serde_json::Value =
+        serde_json::from_str(raw).context("Failed to parse test case corpus")?;
+    let array = value
+        .as_array()
+        .ok_or_else(|| err_msg("Expected a JSON array of test cases"))?;
+
+    Ok(array.iter().filter_map(TestCase::from_json_value).collect())
+}
+
 #[derive(Eq, PartialEq)]
 enum State {
     Editing,
+    EditRules,
+    TestCases,
     SelectPiiRule {
         request: PiiRulesRequest,
         suggestions: Vec<PiiRuleSuggestion>,
@@ -316,16 +645,456 @@ impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             State::Editing => write!(f, "editing")?,
+            State::EditRules => write!(f, "edit-rules")?,
+            State::TestCases => write!(f, "test-cases")?,
             State::SelectPiiRule { .. } => write!(f, "select-pii-rule")?,
         }
         Ok(())
     }
 }
 
+#[derive(Clone, Eq, PartialEq)]
+struct RuleRow {
+    name: String,
+    rule_type: String,
+    pattern: String,
+    key_pattern: String,
+    method: String,
+    alias_target: String,
+}
+
+impl Default for RuleRow {
+    fn default() -> Self {
+        RuleRow {
+            name: String::new(),
+            rule_type: "pattern".to_owned(),
+            pattern: String::new(),
+            key_pattern: String::new(),
+            method: "replace".to_owned(),
+            alias_target: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq)]
+struct ApplicationRow {
+    pii_kind: String,
+    rules: Vec<String>,
+}
+
+fn rule_value_to_row(name: &str, value: &serde_json::Value) -> RuleRow {
+    match value {
+        serde_json::Value::String(alias) => RuleRow {
+            name: name.to_owned(),
+            rule_type: "alias".to_owned(),
+            alias_target: alias.clone(),
+            ..RuleRow::default()
+        },
+        serde_json::Value::Object(obj) => RuleRow {
+            name: name.to_owned(),
+            rule_type: obj
+                .get("type")
+                .and_then(|x| x.as_str())
+                .unwrap_or("pattern")
+                .to_owned(),
+            pattern: obj
+                .get("pattern")
+                .and_then(|x| x.as_str())
+                .unwrap_or("")
+                .to_owned(),
+            key_pattern: obj
+                .get("keyPattern")
+                .and_then(|x| x.as_str())
+                .unwrap_or("")
+                .to_owned(),
+            method: obj
+                .get("redaction")
+                .and_then(|x| x.get("method"))
+                .and_then(|x| x.as_str())
+                .unwrap_or("replace")
+                .to_owned(),
+            ..RuleRow::default()
+        },
+        _ => RuleRow {
+            name: name.to_owned(),
+            ..RuleRow::default()
+        },
+    }
+}
+
+fn rule_row_to_value(row: &RuleRow) -> serde_json::Value {
+    match row.rule_type.as_str() {
+        "alias" => serde_json::Value::String(row.alias_target.clone()),
+        "redactPair" => json!({
+            "type": "redactPair",
+            "keyPattern": row.key_pattern,
+            "redaction": { "method": row.method },
+        }),
+        _ => json!({
+            "type": "pattern",
+            "pattern": row.pattern,
+            "redaction": { "method": row.method },
+        }),
+    }
+}
+
+struct RulesEditor {
+    config: PiiConfig,
+    rules: Vec<RuleRow>,
+    applications: Vec<ApplicationRow>,
+    known_rules: Vec<String>,
+}
+
+impl RulesEditor {
+    fn from_config(config: &PiiConfig) -> RulesEditor {
+        let parsed: serde_json::Value =
+            serde_json::from_str(&config.0).unwrap_or_else(|_| json!({}));
+
+        let rules: Vec<RuleRow> = parsed
+            .get("rules")
+            .and_then(|x| x.as_object())
+            .map(|map| {
+                map.iter()
+                    .map(|(name, value)| rule_value_to_row(name, value))
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new);
+
+        let applications: Vec<ApplicationRow> = parsed
+            .get("applications")
+            .and_then(|x| x.as_object())
+            .map(|map| {
+                map.iter()
+                    .map(|(pii_kind, rules)| ApplicationRow {
+                        pii_kind: pii_kind.to_owned(),
+                        rules: rules
+                            .as_array()
+                            .map(|array| {
+                                array
+                                    .iter()
+                                    .filter_map(|x| x.as_str().map(str::to_owned))
+                                    .collect()
+                            })
+                            .unwrap_or_else(Vec::new),
+                    })
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new);
+
+        let mut known_rules: Vec<String> =
+            BUILTIN_RULES.iter().map(|x| (*x).to_owned()).collect();
+        known_rules.extend(rules.iter().map(|rule| rule.name.clone()));
+
+        RulesEditor {
+            config: config.clone(),
+            rules,
+            applications,
+            known_rules,
+        }
+    }
+
+    fn parsed(&self) -> serde_json::Map<String, serde_json::Value> {
+        match serde_json::from_str(&self.config.0) {
+            Ok(serde_json::Value::Object(x)) => x,
+            _ => serde_json::Map::new(),
+        }
+    }
+
+    fn with_rule_set(&self, name: &str, row: RuleRow) -> PiiConfig {
+        let mut parsed = self.parsed();
+        parsed
+            .entry("rules")
+            .or_insert(json!({}))
+            .as_object_mut()
+            .map(|rules| rules.insert(name.to_owned(), rule_row_to_value(&row)));
+        PiiConfig(serde_json::to_string_pretty(&parsed).unwrap_or_default())
+    }
+
+    fn with_rule_removed(&self, name: &str) -> PiiConfig {
+        let mut parsed = self.parsed();
+        parsed
+            .get_mut("rules")
+            .and_then(|x| x.as_object_mut())
+            .map(|rules| rules.remove(name));
+        PiiConfig(serde_json::to_string_pretty(&parsed).unwrap_or_default())
+    }
+
+    fn with_rule_added(&self) -> PiiConfig {
+        let mut n = self.rules.len();
+        let mut name = format!("rule_{}", n);
+        while self.rules.iter().any(|rule| rule.name == name) {
+            n += 1;
+            name = format!("rule_{}", n);
+        }
+        self.with_rule_set(&name, RuleRow::default())
+    }
+
+    fn with_application_set(&self, pii_kind: &str, rules: Vec<String>) -> PiiConfig {
+        let mut parsed = self.parsed();
+        parsed
+            .entry("applications")
+            .or_insert(json!({}))
+            .as_object_mut()
+            .map(|applications| {
+                applications.insert(
+                    pii_kind.to_owned(),
+                    serde_json::Value::Array(
+                        rules.into_iter().map(serde_json::Value::String).collect(),
+                    ),
+                )
+            });
+        PiiConfig(serde_json::to_string_pretty(&parsed).unwrap_or_default())
+    }
+
+    fn with_application_removed(&self, pii_kind: &str) -> PiiConfig {
+        let mut parsed = self.parsed();
+        parsed
+            .get_mut("applications")
+            .and_then(|x| x.as_object_mut())
+            .map(|applications| applications.remove(pii_kind));
+        PiiConfig(serde_json::to_string_pretty(&parsed).unwrap_or_default())
+    }
+
+    fn with_application_added(&self) -> PiiConfig {
+        let used: Vec<&str> = self
+            .applications
+            .iter()
+            .map(|row| row.pii_kind.as_str())
+            .collect();
+        let pii_kind = PII_KINDS
+            .iter()
+            .find(|kind| !used.contains(kind))
+            .unwrap_or(&PII_KINDS[0]);
+        self.with_application_set(pii_kind, vec![])
+    }
+}
+
+impl Renderable<PiiDemo> for RulesEditor {
+    fn view(&self) -> Html<PiiDemo> {
+        let editor = self.config.clone();
+
+        let rule_rows = self.rules.iter().map(|rule| {
+            let name = rule.name.clone();
+
+            let set_type = {
+                let editor = RulesEditor::from_config(&editor);
+                let rule = rule.clone();
+                move |rule_type: String| {
+                    let mut rule = rule.clone();
+                    rule.rule_type = rule_type;
+                    editor.with_rule_set(&rule.name, rule)
+                }
+            };
+            let set_pattern = {
+                let editor = RulesEditor::from_config(&editor);
+                let rule = rule.clone();
+                move |pattern: String| {
+                    let mut rule = rule.clone();
+                    rule.pattern = pattern;
+                    editor.with_rule_set(&rule.name, rule)
+                }
+            };
+            let set_key_pattern = {
+                let editor = RulesEditor::from_config(&editor);
+                let rule = rule.clone();
+                move |key_pattern: String| {
+                    let mut rule = rule.clone();
+                    rule.key_pattern = key_pattern;
+                    editor.with_rule_set(&rule.name, rule)
+                }
+            };
+            let set_method = {
+                let editor = RulesEditor::from_config(&editor);
+                let rule = rule.clone();
+                move |method: String| {
+                    let mut rule = rule.clone();
+                    rule.method = method;
+                    editor.with_rule_set(&rule.name, rule)
+                }
+            };
+            let set_alias = {
+                let editor = RulesEditor::from_config(&editor);
+                let rule = rule.clone();
+                move |alias_target: String| {
+                    let mut rule = rule.clone();
+                    rule.alias_target = alias_target;
+                    editor.with_rule_set(&rule.name, rule)
+                }
+            };
+            let remove_editor = RulesEditor::from_config(&editor);
+
+            let type_specific_fields = match rule.rule_type.as_str() {
+                "redactPair" => html! {
+                    <span>
+                        <input type="text",
+                            placeholder="key pattern",
+                            value=&rule.key_pattern,
+                            oninput=|e| Msg::PiiConfigChanged(set_key_pattern(e.value)), />
+                        <select onchange=|e| {
+                            if let ChangeData::Select(x) = e { Msg::PiiConfigChanged(set_method(x.value().unwrap_or_default())) }
+                            else { Msg::StartEditing }
+                        },>
+                            <option value="mask", selected=rule.method == "mask",>{ "mask" }</option>
+                            <option value="hash", selected=rule.method == "hash",>{ "hash" }</option>
+                            <option value="replace", selected=rule.method == "replace",>{ "replace" }</option>
+                            <option value="remove", selected=rule.method == "remove",>{ "remove" }</option>
+                        </select>
+                    </span>
+                },
+                "alias" => html! {
+                    <input type="text",
+                        placeholder="existing rule name",
+                        value=&rule.alias_target,
+                        oninput=|e| Msg::PiiConfigChanged(set_alias(e.value)), />
+                },
+                _ => html! {
+                    <span>
+                        <input type="text",
+                            placeholder="regex pattern",
+                            value=&rule.pattern,
+                            oninput=|e| Msg::PiiConfigChanged(set_pattern(e.value)), />
+                        <select onchange=|e| {
+                            if let ChangeData::Select(x) = e { Msg::PiiConfigChanged(set_method(x.value().unwrap_or_default())) }
+                            else { Msg::StartEditing }
+                        },>
+                            <option value="mask", selected=rule.method == "mask",>{ "mask" }</option>
+                            <option value="hash", selected=rule.method == "hash",>{ "hash" }</option>
+                            <option value="replace", selected=rule.method == "replace",>{ "replace" }</option>
+                            <option value="remove", selected=rule.method == "remove",>{ "remove" }</option>
+                        </select>
+                    </span>
+                },
+            };
+
+            html! {
+                <li class="rule-row",>
+                    <code>{ &name }</code>
+                    <select onchange=|e| {
+                        if let ChangeData::Select(x) = e { Msg::PiiConfigChanged(set_type(x.value().unwrap_or_default())) }
+                        else { Msg::StartEditing }
+                    },>
+                        <option value="pattern", selected=rule.rule_type == "pattern",>{ "pattern" }</option>
+                        <option value="redactPair", selected=rule.rule_type == "redactPair",>{ "redactPair" }</option>
+                        <option value="alias", selected=rule.rule_type == "alias",>{ "alias" }</option>
+                    </select>
+                    { type_specific_fields }
+                    <a class="remove-row",
+                        onclick=move |_| Msg::PiiConfigChanged(remove_editor.with_rule_removed(&name)),>
+                        { "remove" }
+                    </a>
+                </li>
+            }
+        });
+
+        let application_rows = self.applications.iter().map(|application| {
+            let pii_kind = application.pii_kind.clone();
+            let known_rules = self.known_rules.clone();
+
+            let set_kind = {
+                let editor = RulesEditor::from_config(&editor);
+                let application = application.clone();
+                move |new_kind: String| {
+                    let removed = editor.with_application_removed(&application.pii_kind);
+                    RulesEditor::from_config(&removed).with_application_set(&new_kind, application.rules.clone())
+                }
+            };
+            let toggle_rule = {
+                let editor = RulesEditor::from_config(&editor);
+                let application = application.clone();
+                move |rule_name: String| {
+                    let mut rules = application.rules.clone();
+                    if let Some(pos) = rules.iter().position(|x| *x == rule_name) {
+                        rules.remove(pos);
+                    } else {
+                        rules.push(rule_name);
+                    }
+                    editor.with_application_set(&application.pii_kind, rules)
+                }
+            };
+            let remove_editor = RulesEditor::from_config(&editor);
+            let remove_kind = pii_kind.clone();
+
+            html! {
+                <li class="application-row",>
+                    <select onchange=|e| {
+                        if let ChangeData::Select(x) = e { Msg::PiiConfigChanged(set_kind(x.value().unwrap_or_default())) }
+                        else { Msg::StartEditing }
+                    },>
+                        { for PII_KINDS.iter().map(|kind| html! {
+                            <option value=kind, selected=*kind == pii_kind,>{ kind }</option>
+                        }) }
+                    </select>
+                    <ul class="rule-checkboxes",>
+                        {
+                            for known_rules.iter().map(|rule| {
+                                let rule = rule.clone();
+                                let selected = application.rules.contains(&rule);
+                                let toggle_rule = toggle_rule.clone();
+                                let rule_to_toggle = rule.clone();
+                                html! {
+                                    <li>
+                                        <label>
+                                            <input type="checkbox",
+                                                checked=selected,
+                                                onclick=move |_| Msg::PiiConfigChanged(toggle_rule(rule_to_toggle.clone())), />
+                                            { &rule }
+                                        </label>
+                                    </li>
+                                }
+                            })
+                        }
+                    </ul>
+                    <a class="remove-row",
+                        onclick=move |_| Msg::PiiConfigChanged(remove_editor.with_application_removed(&remove_kind)),>
+                        { "remove" }
+                    </a>
+                </li>
+            }
+        });
+
+        let add_rule_editor = RulesEditor::from_config(&editor);
+        let add_application_editor = RulesEditor::from_config(&editor);
+
+        html! {
+            <div class="rules-editor",>
+                <h2>{ "Rules" }</h2>
+                <ul>{ for rule_rows }</ul>
+                <a class="add-row",
+                    onclick=move |_| Msg::PiiConfigChanged(add_rule_editor.with_rule_added()),>
+                    { "+ add rule" }
+                </a>
+                <h2>{ "Applications" }</h2>
+                <ul>{ for application_rows }</ul>
+                <a class="add-row",
+                    onclick=move |_| Msg::PiiConfigChanged(add_application_editor.with_application_added()),>
+                    { "+ add application" }
+                </a>
+            </div>
+        }
+    }
+}
+
 struct PiiDemo {
     event: String,
     config: PiiConfig,
+    config_format: ConfigFormat,
+    config_draft: String,
+    config_parse_error: Option<String>,
     state: State,
+    test_cases: Vec<TestCase>,
+    new_test_name: String,
+    new_test_event: String,
+    new_test_path: String,
+    new_test_expected: String,
+    test_cases_import_draft: String,
+    test_cases_import_error: Option<String>,
+    link: ComponentLink<PiiDemo>,
+    fetch_task: Option<FetchTask>,
+    pending_share_key: Option<(Vec<u8>, Vec<u8>)>,
+    share_ciphertext: Option<String>,
+    share_fragment: Option<String>,
+    share_upload_url: String,
+    share_error: Option<String>,
 }
 
 impl PiiDemo {
@@ -337,6 +1106,125 @@ impl PiiDemo {
         let stripped_event = self.config.strip_event(&event)?;
         Ok(stripped_event)
     }
+    fn sync_permalink(&self) {
+        match encode_permalink(&self.event, &self.config.0) {
+            Ok(fragment) => set_location_fragment(&fragment),
+            Err(e) => {
+                // The playground still works without a shareable link, so
+                // don't interrupt the user over it.
+                eprintln!("Failed to update permalink: {:?}", e);
+            }
+        }
+    }
+    fn fetch_encrypted_snapshot(&mut self, key: Vec<u8>, nonce: Vec<u8>) {
+        let url = match get_share_source_url() {
+            Some(url) => url,
+            None => {
+                self.share_error = Some("No ciphertext location in the URL".to_owned());
+                return;
+            }
+        };
+
+        let callback = self.link.send_back(
+            move |response: Response<Text>| {
+                let (meta, body) = response.into_parts();
+                match body {
+                    Ok(text) if meta.status.is_success() => {
+                        Msg::EncryptedSnapshotFetched(Ok(text))
+                    }
+                    _ => Msg::EncryptedSnapshotFetched(Err(
+                        "Failed to fetch the encrypted snapshot".to_owned()
+                    )),
+                }
+            },
+        );
+
+        let request = match Request::get(url.as_str()).body(Nothing) {
+            Ok(request) => request,
+            Err(e) => {
+                self.share_error = Some(format!("{:?}", e));
+                return;
+            }
+        };
+
+        self.pending_share_key = Some((key, nonce));
+        self.fetch_task = Some(FetchService::new().fetch(request, callback));
+    }
+    fn view_test_cases(&self) -> Html<Self> {
+        let rows = self.test_cases.iter().enumerate().map(|(index, case)| {
+            let (class, detail) = match case.run(&self.config) {
+                TestOutcome::Pass => ("test-pass", html! { <span>{ "pass" }</span> }),
+                TestOutcome::Fail(actual) => (
+                    "test-fail",
+                    html! {
+                        <span>{ "fail \u{2014} actual: " }<code>{ actual }</code></span>
+                    },
+                ),
+                TestOutcome::Error(error) => (
+                    "test-error",
+                    html! { <span>{ "error: " }{ error }</span> },
+                ),
+            };
+
+            html! {
+                <li class=class,>
+                    <strong>{ &case.name }</strong>
+                    { " (" }<code>{ &case.path }</code>{ ") " }
+                    { detail }
+                    <a class="remove-row",
+                        onclick=move |_| Msg::RemoveTestCase(index),>
+                        { "remove" }
+                    </a>
+                </li>
+            }
+        });
+
+        html! {
+            <div class="test-cases",>
+                <div class="col-header", onclick=|_| Msg::ToggleTestCases,>
+                    <h1>{ "4. Regression test vectors" }</h1>
+                </div>
+                <ul>{ for rows }</ul>
+                <h2>{ "Add a test case" }</h2>
+                <input type="text",
+                    placeholder="name",
+                    value=&self.new_test_name,
+                    oninput=|e| Msg::NewTestNameChanged(e.value), />
+                <textarea
+                    placeholder="event JSON",
+                    value=&self.new_test_event,
+                    oninput=|e| Msg::NewTestEventChanged(e.value), />
+                <input type="text",
+                    placeholder="path, e.g. extra.foo.3",
+                    value=&self.new_test_path,
+                    oninput=|e| Msg::NewTestPathChanged(e.value), />
+                <input type="text",
+                    placeholder="expected JSON value",
+                    value=&self.new_test_expected,
+                    oninput=|e| Msg::NewTestExpectedChanged(e.value), />
+                <button onclick=|_| Msg::AddTestCase,>{ "Add test case" }</button>
+
+                <h2>{ "Import / export corpus" }</h2>
+                <textarea
+                    placeholder="paste a JSON array of test cases",
+                    value=&self.test_cases_import_draft,
+                    oninput=|e| Msg::TestCasesImportDraftChanged(e.value), />
+                <button onclick=|_| Msg::ImportTestCases,>{ "Import" }</button>
+                <button onclick=|_| Msg::ExportTestCases,>{ "Copy exported JSON" }</button>
+                {
+                    if let Some(ref error) = self.test_cases_import_error {
+                        html! {
+                            <div class="test-cases-import-error",>
+                                { format!("Failed to import test cases: {}", error) }
+                            </div>
+                        }
+                    } else {
+                        "".into()
+                    }
+                }
+            </div>
+        }
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -371,6 +1259,25 @@ enum Msg {
     EventInputChanged(String),
     SelectPiiRule(PiiRulesRequest),
     StartEditing,
+    StartEditingRules,
+    CopyPermalink,
+    ToggleTestCases,
+    NewTestNameChanged(String),
+    NewTestEventChanged(String),
+    NewTestPathChanged(String),
+    NewTestExpectedChanged(String),
+    AddTestCase,
+    RemoveTestCase(usize),
+    TestCasesImportDraftChanged(String),
+    ImportTestCases,
+    ExportTestCases,
+    ConfigFormatChanged(ConfigFormat),
+    ConfigDraftChanged(String),
+    ShareEncrypted,
+    EncryptedSnapshotFetched(Result<String, String>),
+    CopyShareCiphertext,
+    ShareUploadUrlChanged(String),
+    CopyShareLink,
 }
 
 impl Component for PiiDemo {
@@ -379,23 +1286,72 @@ impl Component for PiiDemo {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_: Self::Properties, _: ComponentLink<Self>) -> Self {
-        PiiDemo {
-            config: PiiConfig(DEFAULT_CONFIG.to_owned()),
-            event: DEFAULT_EVENT.to_owned(),
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let fragment = get_location_fragment();
+        let encrypted_share = decode_encrypted_fragment(&fragment);
+
+        let (event, config) = if encrypted_share.is_some() || fragment.is_empty() {
+            (DEFAULT_EVENT.to_owned(), DEFAULT_CONFIG.to_owned())
+        } else {
+            match decode_permalink(&fragment) {
+                Ok((event, config)) => (event, config),
+                Err(e) => {
+                    eprintln!("Failed to load permalink, using defaults: {:?}", e);
+                    (DEFAULT_EVENT.to_owned(), DEFAULT_CONFIG.to_owned())
+                }
+            }
+        };
+
+        let mut demo = PiiDemo {
+            config_draft: config.clone(),
+            config: PiiConfig(config),
+            config_format: ConfigFormat::Json,
+            config_parse_error: None,
+            event,
             state: State::Editing,
+            test_cases: vec![],
+            new_test_name: String::new(),
+            new_test_event: String::new(),
+            new_test_path: String::new(),
+            new_test_expected: String::new(),
+            test_cases_import_draft: String::new(),
+            test_cases_import_error: None,
+            link,
+            fetch_task: None,
+            pending_share_key: None,
+            share_ciphertext: None,
+            share_fragment: None,
+            share_upload_url: String::new(),
+            share_error: None,
+        };
+
+        if let Some((key, nonce)) = encrypted_share {
+            demo.fetch_encrypted_snapshot(key, nonce);
         }
+
+        demo
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::PiiConfigChanged(value) => {
                 self.config = value;
-                self.state = State::Editing;
+                self.config_draft = self
+                    .config
+                    .to_format(self.config_format)
+                    .unwrap_or_else(|_| self.config.0.clone());
+                self.config_parse_error = None;
+                self.state = if self.state == State::EditRules {
+                    State::EditRules
+                } else {
+                    State::Editing
+                };
+                self.sync_permalink();
             }
             Msg::EventInputChanged(value) => {
                 self.event = value;
                 self.state = State::Editing;
+                self.sync_permalink();
             }
             Msg::SelectPiiRule(request) => {
                 let suggestions = request.get_suggestions(&self);
@@ -410,6 +1366,194 @@ impl Component for PiiDemo {
                 }
                 self.state = State::Editing;
             }
+            Msg::StartEditingRules => {
+                if self.state == State::EditRules {
+                    return false;
+                }
+                self.state = State::EditRules;
+            }
+            Msg::CopyPermalink => {
+                js! {
+                    var link = window.location.href;
+                    if (navigator.clipboard) {
+                        navigator.clipboard.writeText(link);
+                    } else {
+                        window.prompt("Copy this link:", link);
+                    }
+                }
+                return false;
+            }
+            Msg::ToggleTestCases => {
+                self.state = if self.state == State::TestCases {
+                    State::Editing
+                } else {
+                    State::TestCases
+                };
+            }
+            Msg::NewTestNameChanged(value) => {
+                self.new_test_name = value;
+            }
+            Msg::NewTestEventChanged(value) => {
+                self.new_test_event = value;
+            }
+            Msg::NewTestPathChanged(value) => {
+                self.new_test_path = value;
+            }
+            Msg::NewTestExpectedChanged(value) => {
+                self.new_test_expected = value;
+            }
+            Msg::AddTestCase => {
+                if self.new_test_name.is_empty() {
+                    return false;
+                }
+
+                self.test_cases.push(TestCase {
+                    name: self.new_test_name.clone(),
+                    event_json: self.new_test_event.clone(),
+                    path: self.new_test_path.clone(),
+                    expected: self.new_test_expected.clone(),
+                });
+
+                self.new_test_name.clear();
+                self.new_test_event.clear();
+                self.new_test_path.clear();
+                self.new_test_expected.clear();
+            }
+            Msg::RemoveTestCase(index) => {
+                if index < self.test_cases.len() {
+                    self.test_cases.remove(index);
+                }
+            }
+            Msg::TestCasesImportDraftChanged(value) => {
+                self.test_cases_import_draft = value;
+            }
+            Msg::ImportTestCases => match test_cases_from_json(&self.test_cases_import_draft) {
+                Ok(cases) => {
+                    self.test_cases = cases;
+                    self.test_cases_import_error = None;
+                }
+                Err(e) => self.test_cases_import_error = Some(format!("{:?}", e)),
+            },
+            Msg::ExportTestCases => {
+                let exported = test_cases_to_json(&self.test_cases);
+                js! {
+                    var exported = @{exported};
+                    if (navigator.clipboard) {
+                        navigator.clipboard.writeText(exported);
+                    } else {
+                        window.prompt("Copy the exported test cases:", exported);
+                    }
+                }
+                return false;
+            }
+            Msg::ConfigFormatChanged(format) => {
+                self.config_format = format;
+                match self.config.to_format(format) {
+                    Ok(text) => {
+                        self.config_draft = text;
+                        self.config_parse_error = None;
+                    }
+                    Err(e) => {
+                        self.config_parse_error = Some(format!("{:?}", e));
+                    }
+                }
+            }
+            Msg::ConfigDraftChanged(value) => {
+                self.config_draft = value.clone();
+
+                match PiiConfig::from_format(&value, self.config_format) {
+                    Ok(config) => {
+                        self.config = config;
+                        self.config_parse_error = None;
+                        self.state = if self.state == State::EditRules {
+                            State::EditRules
+                        } else {
+                            State::Editing
+                        };
+                        self.sync_permalink();
+                    }
+                    Err(e) => {
+                        self.config_parse_error = Some(format!("{:?}", e));
+                    }
+                }
+            }
+            Msg::ShareEncrypted => match encrypt_snapshot(&self.event, &self.config.0) {
+                Ok((ciphertext, fragment)) => {
+                    self.share_ciphertext = Some(ciphertext);
+                    self.share_fragment = Some(fragment);
+                    self.share_error = None;
+                }
+                Err(e) => {
+                    self.share_error = Some(format!("{:?}", e));
+                }
+            },
+            Msg::EncryptedSnapshotFetched(result) => {
+                self.fetch_task = None;
+
+                let (key, nonce) = match self.pending_share_key.take() {
+                    Some(x) => x,
+                    None => return false,
+                };
+
+                match result {
+                    Ok(ciphertext) => match decrypt_snapshot(&ciphertext, &key, &nonce) {
+                        Ok((event, config)) => {
+                            self.event = event;
+                            self.config = PiiConfig(config);
+                            self.config_draft = self.config.0.clone();
+                            self.config_parse_error = None;
+                            self.share_error = None;
+                        }
+                        Err(e) => {
+                            self.share_error = Some(format!("{:?}", e));
+                        }
+                    },
+                    Err(e) => {
+                        self.share_error = Some(e);
+                    }
+                }
+            }
+            Msg::CopyShareCiphertext => {
+                if let Some(ref ciphertext) = self.share_ciphertext {
+                    js! {
+                        var ciphertext = @{ciphertext};
+                        if (navigator.clipboard) {
+                            navigator.clipboard.writeText(ciphertext);
+                        } else {
+                            window.prompt("Copy this ciphertext to your host of choice:", ciphertext);
+                        }
+                    }
+                }
+                return false;
+            }
+            Msg::ShareUploadUrlChanged(value) => {
+                self.share_upload_url = value;
+                self.share_error = None;
+            }
+            Msg::CopyShareLink => {
+                if let Some(ref fragment) = self.share_fragment {
+                    if self.share_upload_url.trim().is_empty() {
+                        self.share_error = Some(
+                            "Paste the URL where you uploaded the ciphertext before copying the link"
+                                .to_owned(),
+                        );
+                        return true;
+                    }
+
+                    let src = self.share_upload_url.clone();
+                    js! {
+                        var src = @{src};
+                        var link = window.location.origin + window.location.pathname
+                            + "?src=" + encodeURIComponent(src) + "#" + @{fragment};
+                        if (navigator.clipboard) {
+                            navigator.clipboard.writeText(link);
+                        } else {
+                            window.prompt("Copy this link:", link);
+                        }
+                    }
+                }
+                return false;
+            }
         }
 
         true
@@ -458,14 +1602,126 @@ impl Renderable<PiiDemo> for PiiDemo {
                         <div
                             class="col-header",
                             onclick=|_| Msg::StartEditing, >
-                            <h1>{ "3. Copy the PII config." }</h1>
+                            <h1>
+                                { "3. Copy the PII config." }
+                                <br/>
+                                <small>
+                                    <a class="copy-link",
+                                        onclick=|_| Msg::CopyPermalink,>
+                                        { "Copy link to this scenario" }
+                                    </a>
+                                    { " \u{00b7} " }
+                                    <a class="toggle-test-cases",
+                                        onclick=|_| Msg::ToggleTestCases,>
+                                        { "Test vectors" }
+                                    </a>
+                                    { " \u{00b7} " }
+                                    <a class="share-encrypted",
+                                        onclick=|_| Msg::ShareEncrypted,>
+                                        { "Share encrypted" }
+                                    </a>
+                                    { " \u{00b7} " }
+                                    {
+                                        if self.state == State::EditRules {
+                                            html! {
+                                                <a class="toggle-editor",
+                                                    onclick=|_| Msg::StartEditing,>
+                                                    { "Edit raw config" }
+                                                </a>
+                                            }
+                                        } else {
+                                            html! {
+                                                <a class="toggle-editor",
+                                                    onclick=|_| Msg::StartEditingRules,>
+                                                    { "Edit rules visually" }
+                                                </a>
+                                            }
+                                        }
+                                    }
+                                    { " \u{00b7} " }
+                                    <select onchange=|e| {
+                                        if let ChangeData::Select(x) = e {
+                                            match x.value().unwrap_or_default().parse() {
+                                                Ok(format) => Msg::ConfigFormatChanged(format),
+                                                Err(_) => Msg::StartEditing,
+                                            }
+                                        } else {
+                                            Msg::StartEditing
+                                        }
+                                    },>
+                                        <option value="json", selected=self.config_format == ConfigFormat::Json,>{ "JSON" }</option>
+                                        <option value="yaml", selected=self.config_format == ConfigFormat::Yaml,>{ "YAML" }</option>
+                                        <option value="toml", selected=self.config_format == ConfigFormat::Toml,>{ "TOML" }</option>
+                                    </select>
+                                </small>
+                            </h1>
                         </div>
+                        {
+                            if self.state == State::EditRules {
+                                RulesEditor::from_config(&self.config).view()
+                            } else {
+                                "".into()
+                            }
+                        }
+                        {
+                            if let Some(ref error) = self.config_parse_error {
+                                html! {
+                                    <div class="config-error",>
+                                        { format!("Failed to parse {}: {}", self.config_format.label(), error) }
+                                    </div>
+                                }
+                            } else {
+                                "".into()
+                            }
+                        }
+                        {
+                            if let Some(ref error) = self.share_error {
+                                html! {
+                                    <div class="share-error",>
+                                        { error }
+                                    </div>
+                                }
+                            } else {
+                                "".into()
+                            }
+                        }
+                        {
+                            if let Some(ref ciphertext) = self.share_ciphertext {
+                                html! {
+                                    <div class="share-encrypted-output",>
+                                        <p>
+                                            { "Upload this ciphertext anywhere (e.g. a gist), paste the URL " }
+                                            { "it is served from below, and share the link that \"Copy link\" builds. " }
+                                            { "The decryption key only ever lives in the fragment, so " }
+                                            { "the host never learns what's in the snapshot." }
+                                        </p>
+                                        <textarea class="share-ciphertext", readonly=true, value=ciphertext, />
+                                        <a onclick=|_| Msg::CopyShareCiphertext,>{ "Copy ciphertext" }</a>
+                                        <br/>
+                                        <input type="text",
+                                            placeholder="URL where you uploaded the ciphertext",
+                                            value=&self.share_upload_url,
+                                            oninput=|e| Msg::ShareUploadUrlChanged(e.value), />
+                                        <a onclick=|_| Msg::CopyShareLink,>{ "Copy link" }</a>
+                                    </div>
+                                }
+                            } else {
+                                "".into()
+                            }
+                        }
                         <textarea
                             class="col-body",
-                            value=&self.config.0,
+                            value=&self.config_draft,
                             onfocus=|_| Msg::StartEditing,
-                            oninput=|e| Msg::PiiConfigChanged(PiiConfig(e.value)), />
+                            oninput=|e| Msg::ConfigDraftChanged(e.value), />
                     </div>
+                    {
+                        if self.state == State::TestCases {
+                            html! { <div class="col",>{ self.view_test_cases() }</div> }
+                        } else {
+                            "".into()
+                        }
+                    }
                 </div>
             </div>
         }
@@ -476,6 +1732,8 @@ impl Renderable<PiiDemo> for State {
     fn view(&self) -> Html<PiiDemo> {
         match *self {
             State::Editing => "".into(),
+            State::EditRules => "".into(),
+            State::TestCases => "".into(),
             State::SelectPiiRule {
                 ref request,
                 ref suggestions,